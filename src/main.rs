@@ -1,7 +1,11 @@
 use std::{
+	collections::HashMap,
 	fs::File,
 	io::Read,
-	os::{fd::AsFd, unix::fs::FileTypeExt},
+	os::{
+		fd::{AsFd, AsRawFd},
+		unix::fs::FileTypeExt,
+	},
 	path::{Path, PathBuf},
 	time::{Duration, Instant},
 };
@@ -11,9 +15,70 @@ use nix::{
 	fcntl::{FcntlArg, OFlag, fcntl},
 	poll::PollTimeout,
 	sys::epoll::{Epoll, EpollCreateFlags, EpollEvent, EpollFlags},
+	sys::inotify::{AddWatchFlags, InitFlags, Inotify},
+	sys::signal::{SigSet, SigmaskHow, Signal, sigprocmask},
+	sys::signalfd::{SfdFlags, SignalFd},
 };
 
 const MIN_FADE_TICK_MS: u64 = 16; // 60Hz should be plenty fast
+const AMBIENT_SAMPLE_MS: u64 = 1_000; // ambient light doesn't change fast
+
+// Event types from linux/input-event-codes.h that we know how to filter on.
+const EV_KEY: u16 = 0x01;
+const EV_REL: u16 = 0x02;
+const EV_ABS: u16 = 0x03;
+const EV_MAX: u16 = 0x1f;
+const KEY_MAX: u16 = 0x2ff;
+const KEY_A: u16 = 30;
+const KEY_Z: u16 = 44;
+
+const EV_TYPES_BYTES: usize = (EV_MAX as usize + 1).div_ceil(8);
+const KEY_CODES_BYTES: usize = (KEY_MAX as usize + 1).div_ceil(8);
+
+// EVIOCGBIT(0, len): which event types (EV_KEY, EV_REL, ...) a device supports.
+nix::ioctl_read_buf!(eviocgbit_ev_types, b'E', 0x20, u8);
+// EVIOCGBIT(EV_KEY, len): which key/button codes a device supports.
+nix::ioctl_read_buf!(eviocgbit_key_codes, b'E', 0x20 + EV_KEY as u8, u8);
+// EVIOCGNAME(len): the device's human-readable name.
+nix::ioctl_read_buf!(eviocgname, b'E', 0x06, u8);
+
+// Epoll data values for the non-device fds, chosen far outside the range of
+// incrementing device ids so they can never collide with one.
+const INOTIFY_TOKEN: u64 = u64::MAX;
+const SIGNAL_TOKEN: u64 = u64::MAX - 1;
+
+fn bit_set(bits: &[u8], n: u16) -> bool {
+	let byte = n as usize / 8;
+	let bit = n as usize % 8;
+	bits.get(byte).is_some_and(|b| b & (1 << bit) != 0)
+}
+
+fn is_keyboard_device(f: &File) -> bool {
+	let mut ev_types = [0u8; EV_TYPES_BYTES];
+	if unsafe { eviocgbit_ev_types(f.as_raw_fd(), &mut ev_types) }.is_err() {
+		return false;
+	}
+	if !bit_set(&ev_types, EV_KEY) {
+		return false;
+	}
+
+	let mut key_codes = [0u8; KEY_CODES_BYTES];
+	if unsafe { eviocgbit_key_codes(f.as_raw_fd(), &mut key_codes) }.is_err() {
+		return false;
+	}
+
+	// Require the alphabetic keys, which a pointer device's handful of
+	// EV_KEY button codes (BTN_LEFT and friends) won't have.
+	bit_set(&key_codes, KEY_A) && bit_set(&key_codes, KEY_Z)
+}
+
+fn device_name(f: &File) -> Option<String> {
+	let mut buf = [0u8; 256];
+	let n = unsafe { eviocgname(f.as_raw_fd(), &mut buf) }.ok()?;
+	let n = (n.max(0) as usize).min(buf.len());
+	let end = buf[..n].iter().position(|&b| b == 0).unwrap_or(n);
+	Some(String::from_utf8_lossy(&buf[..end]).into_owned())
+}
 
 #[derive(Parser)]
 struct Options {
@@ -40,6 +105,215 @@ struct Options {
 	fade_in_ms: u64,
 	#[arg(short = 'v', long = "verbose", default_value_t = false)]
 	verbose: bool,
+	#[arg(
+		short = 't',
+		long = "trigger",
+		help = "Comma-separated input event types that count as activity (key, rel, abs)",
+		default_value = "key"
+	)]
+	trigger: ActivityFilter,
+	#[arg(
+		long = "match-name",
+		help = "Only watch input devices whose EVIOCGNAME contains this substring"
+	)]
+	match_name: Option<String>,
+	#[arg(
+		long = "ambient",
+		help = "Scale active brightness to ambient light from an IIO illuminance sensor, if one is found",
+		default_value_t = false
+	)]
+	ambient: bool,
+	#[arg(
+		long = "lux-min",
+		help = "Lux value that maps to minimum active brightness",
+		default_value_t = 10.0
+	)]
+	lux_min: f32,
+	#[arg(
+		long = "lux-max",
+		help = "Lux value that maps to maximum active brightness",
+		default_value_t = 1000.0
+	)]
+	lux_max: f32,
+	#[arg(
+		long = "ambient-alpha",
+		help = "EMA smoothing factor applied to ambient light samples",
+		default_value_t = 0.1
+	)]
+	ambient_alpha: f32,
+	#[arg(
+		long = "curve",
+		help = "Fade transfer function applied to the raw brightness: linear, gamma, or cie",
+		default_value = "linear"
+	)]
+	curve: Curve,
+	#[arg(
+		long = "gamma",
+		help = "Exponent used by --curve gamma (must be > 0.0)",
+		default_value_t = 2.2,
+		value_parser = parse_gamma
+	)]
+	gamma: f32,
+	#[arg(
+		long = "color",
+		help = "Active (non-idle) color as R,G,B, for keyboards with multi_intensity color channels",
+		default_value = "255,255,255"
+	)]
+	color: Color,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Color {
+	r: u8,
+	g: u8,
+	b: u8,
+}
+
+impl std::str::FromStr for Color {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let parts: Vec<&str> = s.split(',').collect();
+		let [r, g, b] = parts.as_slice() else {
+			return Err(format!("expected a color as 'R,G,B', got '{s}'"));
+		};
+
+		let parse_channel = |tok: &str| {
+			tok.trim()
+				.parse::<u8>()
+				.map_err(|e| format!("invalid color channel '{tok}': {e}"))
+		};
+
+		Ok(Self {
+			r: parse_channel(r)?,
+			g: parse_channel(g)?,
+			b: parse_channel(b)?,
+		})
+	}
+}
+
+fn parse_gamma(s: &str) -> Result<f32, String> {
+	let gamma: f32 = s.parse().map_err(|e| format!("invalid gamma '{s}': {e}"))?;
+	if gamma > 0.0 {
+		Ok(gamma)
+	} else {
+		Err(format!("gamma must be > 0.0, got {gamma}"))
+	}
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Curve {
+	Linear,
+	Gamma,
+	Cie,
+}
+
+impl Curve {
+	fn apply(self, t: f32, gamma: f32) -> f32 {
+		let t = clamp01(t);
+		match self {
+			Curve::Linear => t,
+			Curve::Gamma => t.powf(gamma),
+			Curve::Cie => cie_lstar_to_linear(t),
+		}
+	}
+
+	// Inverse of apply(), used to recover the perceptual fade parameter
+	// from a raw brightness read off disk (e.g. at startup).
+	fn invert(self, y: f32, gamma: f32) -> f32 {
+		let y = clamp01(y);
+		match self {
+			Curve::Linear => y,
+			Curve::Gamma => y.powf(1.0 / gamma),
+			Curve::Cie => linear_to_cie_lstar(y),
+		}
+	}
+}
+
+impl std::str::FromStr for Curve {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_ascii_lowercase().as_str() {
+			"linear" => Ok(Curve::Linear),
+			"gamma" => Ok(Curve::Gamma),
+			"cie" => Ok(Curve::Cie),
+			other => Err(format!(
+				"unknown curve '{other}', expected one of: linear, gamma, cie"
+			)),
+		}
+	}
+}
+
+// CIE L* companding: l_star_fraction is a 0..1 fraction of the 0..100 L*
+// lightness axis; convert it to linear luminance Y.
+fn cie_lstar_to_linear(l_star_fraction: f32) -> f32 {
+	let l_star = clamp01(l_star_fraction) * 100.0;
+	let y = if l_star > 8.0 {
+		((l_star + 16.0) / 116.0).powi(3)
+	} else {
+		l_star / 903.3
+	};
+	clamp01(y)
+}
+
+// Inverse of cie_lstar_to_linear: linear luminance Y back to a 0..1
+// fraction of the L* lightness axis.
+fn linear_to_cie_lstar(y: f32) -> f32 {
+	let y = clamp01(y);
+	let l_star = if y > (6.0f32 / 29.0).powi(3) {
+		116.0 * y.cbrt() - 16.0
+	} else {
+		903.3 * y
+	};
+	clamp01(l_star / 100.0)
+}
+
+#[derive(Clone, Debug)]
+struct ActivityFilter {
+	types: Vec<u16>,
+}
+
+impl ActivityFilter {
+	fn matches(&self, ev_type: u16) -> bool {
+		self.types.contains(&ev_type)
+	}
+}
+
+impl std::str::FromStr for ActivityFilter {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let types = s
+			.split(',')
+			.map(|tok| match tok.trim().to_ascii_lowercase().as_str() {
+				"key" => Ok(EV_KEY),
+				"rel" => Ok(EV_REL),
+				"abs" => Ok(EV_ABS),
+				other => Err(format!(
+					"unknown event type '{other}', expected one of: key, rel, abs"
+				)),
+			})
+			.collect::<Result<Vec<u16>, String>>()?;
+
+		Ok(Self { types })
+	}
+}
+
+// Kernel input_event layout on 64-bit Linux: two i64 timeval fields, then
+// u16 type, u16 code, i32 value (24 bytes total).
+#[derive(Clone, Copy, Debug)]
+struct InputEvent {
+	ev_type: u16,
+}
+
+impl InputEvent {
+	const SIZE: usize = 24;
+
+	fn parse(buf: &[u8; Self::SIZE]) -> Self {
+		let ev_type = u16::from_ne_bytes([buf[16], buf[17]]);
+		Self { ev_type }
+	}
 }
 
 fn to_io_err(e: nix::Error) -> std::io::Error {
@@ -53,6 +327,13 @@ fn read_int(path: &Path) -> std::io::Result<u32> {
 		.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
 }
 
+fn read_f32(path: &Path) -> std::io::Result<f32> {
+	let s = std::fs::read_to_string(path)?;
+	s.trim()
+		.parse::<f32>()
+		.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
 fn clamp01(x: f32) -> f32 {
 	x.clamp(0.0, 1.0)
 }
@@ -105,24 +386,79 @@ fn find_keyboard_backlight_dir() -> std::io::Result<PathBuf> {
 	}
 }
 
+// Color channel ids from the kernel's dt-bindings/leds/common.h, as reported
+// per-entry by `multi_index`.
+const LED_COLOR_ID_WHITE: u32 = 0;
+const LED_COLOR_ID_RED: u32 = 1;
+const LED_COLOR_ID_GREEN: u32 = 2;
+const LED_COLOR_ID_BLUE: u32 = 3;
+
+// Each channel's ratio comes from --color. On every brightness write,
+// multi_intensity is rewritten as ratio * raw per channel, so the channels
+// fade together with brightness and all reach zero when it does.
+struct RgbChannels {
+	multi_intensity_path: PathBuf,
+	ratios: Vec<f32>,
+	last_written: Option<Vec<u32>>,
+}
+
+fn probe_rgb_channels(dir: &Path, color: Color) -> Option<RgbChannels> {
+	let multi_intensity_path = dir.join("multi_intensity");
+	let multi_index_path = dir.join("multi_index");
+	if !multi_intensity_path.is_file() || !multi_index_path.is_file() {
+		return None;
+	}
+
+	let index_str = std::fs::read_to_string(&multi_index_path).ok()?;
+	let ratios: Vec<f32> = index_str
+		.split_whitespace()
+		.map(|tok| match tok.parse::<u32>() {
+			Ok(LED_COLOR_ID_RED) => color.r as f32 / 255.0,
+			Ok(LED_COLOR_ID_GREEN) => color.g as f32 / 255.0,
+			Ok(LED_COLOR_ID_BLUE) => color.b as f32 / 255.0,
+			Ok(LED_COLOR_ID_WHITE) => {
+				(color.r as f32 + color.g as f32 + color.b as f32) / (3.0 * 255.0)
+			}
+			_ => 0.0,
+		})
+		.collect();
+
+	if ratios.is_empty() {
+		return None;
+	}
+
+	Some(RgbChannels {
+		multi_intensity_path,
+		ratios,
+		last_written: None,
+	})
+}
+
 struct Backlight {
 	brightness_path: PathBuf,
 	max_raw: u32,
 	last_raw_written: Option<u32>,
+	curve: Curve,
+	gamma: f32,
+	rgb: Option<RgbChannels>,
 }
 
 impl Backlight {
-	fn open() -> std::io::Result<Self> {
+	fn open(curve: Curve, gamma: f32, color: Color) -> std::io::Result<Self> {
 		let dir = find_keyboard_backlight_dir()?;
 		let brightness_path = dir.join("brightness");
 		let max_path = dir.join("max_brightness");
 
 		let max_raw = read_int(&max_path)?;
+		let rgb = probe_rgb_channels(&dir, color);
 
 		Ok(Self {
 			brightness_path,
 			max_raw,
 			last_raw_written: None,
+			curve,
+			gamma,
+			rgb,
 		})
 	}
 
@@ -131,23 +467,49 @@ impl Backlight {
 	}
 
 	fn write_raw(&mut self, raw: u32) -> std::io::Result<()> {
-		if self.last_raw_written == Some(raw) {
-			return Ok(());
+		if self.last_raw_written != Some(raw) {
+			self.last_raw_written = Some(raw);
+			std::fs::write(&self.brightness_path, format!("{raw}\n"))?;
 		}
-		self.last_raw_written = Some(raw);
 
-		std::fs::write(&self.brightness_path, format!("{raw}\n"))
+		self.write_color(raw)
+	}
+
+	fn write_color(&mut self, raw: u32) -> std::io::Result<()> {
+		let Some(rgb) = &mut self.rgb else {
+			return Ok(());
+		};
+
+		let channels: Vec<u32> = rgb
+			.ratios
+			.iter()
+			.map(|ratio| (ratio * raw as f32).round() as u32)
+			.collect();
+
+		if rgb.last_written.as_deref() == Some(channels.as_slice()) {
+			return Ok(());
+		}
+		rgb.last_written = Some(channels.clone());
+
+		let line = channels
+			.iter()
+			.map(u32::to_string)
+			.collect::<Vec<_>>()
+			.join(" ");
+		std::fs::write(&rgb.multi_intensity_path, format!("{line}\n"))
 	}
 
 	fn raw_to_f32(&self, raw: u32) -> f32 {
 		if self.max_raw == 0 {
 			return 0.0;
 		}
-		clamp01(raw as f32 / self.max_raw as f32)
+		let linear = clamp01(raw as f32 / self.max_raw as f32);
+		self.curve.invert(linear, self.gamma)
 	}
 
 	fn f32_to_raw(&self, v: f32) -> u32 {
-		(clamp01(v) * self.max_raw as f32).round() as u32
+		let mapped = self.curve.apply(v, self.gamma);
+		(mapped * self.max_raw as f32).round() as u32
 	}
 }
 
@@ -224,6 +586,205 @@ impl Fader {
 	}
 }
 
+enum LuxSource {
+	Input(PathBuf),
+	RawScale(PathBuf, f32),
+}
+
+impl LuxSource {
+	fn read_lux(&self) -> std::io::Result<f32> {
+		match self {
+			LuxSource::Input(path) => read_f32(path),
+			LuxSource::RawScale(path, scale) => Ok(read_f32(path)? * scale),
+		}
+	}
+}
+
+fn find_ambient_light_sensor() -> Option<LuxSource> {
+	let base = Path::new("/sys/bus/iio/devices");
+
+	for entry in std::fs::read_dir(base).ok()?.flatten() {
+		let dir = entry.path();
+		let is_iio_device = dir
+			.file_name()
+			.is_some_and(|n| n.to_string_lossy().starts_with("iio:device"));
+		if !is_iio_device {
+			continue;
+		}
+
+		let input_path = dir.join("in_illuminance_input");
+		if input_path.is_file() {
+			return Some(LuxSource::Input(input_path));
+		}
+
+		let raw_path = dir.join("in_illuminance_raw");
+		if raw_path.is_file() {
+			let scale = read_f32(&dir.join("in_illuminance_scale")).unwrap_or(1.0);
+			return Some(LuxSource::RawScale(raw_path, scale));
+		}
+	}
+
+	None
+}
+
+struct AmbientLight {
+	source: LuxSource,
+	ema: f32,
+	alpha: f32,
+	lux_min: f32,
+	lux_max: f32,
+}
+
+impl AmbientLight {
+	fn new(source: LuxSource, alpha: f32, lux_min: f32, lux_max: f32) -> std::io::Result<Self> {
+		let ema = source.read_lux()?;
+		Ok(Self {
+			source,
+			ema,
+			alpha,
+			lux_min,
+			lux_max,
+		})
+	}
+
+	fn sample(&mut self) -> f32 {
+		if let Ok(lux) = self.source.read_lux() {
+			self.ema += self.alpha * (lux - self.ema);
+		}
+
+		let range = (self.lux_max - self.lux_min).max(f32::EPSILON);
+		clamp01((self.ema - self.lux_min) / range)
+	}
+}
+
+struct InputDevice {
+	path: PathBuf,
+	file: File,
+	pending: Vec<u8>,
+}
+
+impl InputDevice {
+	fn new(path: PathBuf, file: File) -> Self {
+		Self {
+			path,
+			file,
+			pending: Vec::new(),
+		}
+	}
+
+	fn drain_activity(&mut self, filter: &ActivityFilter) -> std::io::Result<bool> {
+		let mut chunk = [0u8; 4096];
+		loop {
+			match self.file.read(&mut chunk) {
+				Ok(0) => break,
+				Ok(n) => self.pending.extend_from_slice(&chunk[..n]),
+				Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+				Err(e) => return Err(e),
+			}
+		}
+
+		let mut matched = false;
+		let mut offset = 0;
+		while self.pending.len() - offset >= InputEvent::SIZE {
+			let buf: [u8; InputEvent::SIZE] = self.pending[offset..offset + InputEvent::SIZE]
+				.try_into()
+				.expect("slice length matches InputEvent::SIZE");
+			let ev = InputEvent::parse(&buf);
+			if filter.matches(ev.ev_type) {
+				matched = true;
+			}
+			offset += InputEvent::SIZE;
+		}
+		self.pending.drain(..offset);
+
+		Ok(matched)
+	}
+}
+
+// Ok(None) means path didn't qualify (couldn't be opened, not a keyboard,
+// or didn't match --match-name); Err is returned only when setting
+// O_NONBLOCK on an opened keyboard fails.
+fn open_keyboard_device(path: &Path, match_name: Option<&str>) -> std::io::Result<Option<File>> {
+	let f = match File::open(path) {
+		Ok(f) => f,
+		Err(_) => return Ok(None),
+	};
+
+	if !is_keyboard_device(&f) {
+		return Ok(None);
+	}
+
+	if let Some(needle) = match_name {
+		let name = device_name(&f).unwrap_or_default();
+		if !name.contains(needle) {
+			return Ok(None);
+		}
+	}
+
+	let flags = OFlag::from_bits_truncate(fcntl(f.as_fd(), FcntlArg::F_GETFL).map_err(to_io_err)?);
+	fcntl(f.as_fd(), FcntlArg::F_SETFL(flags | OFlag::O_NONBLOCK)).map_err(to_io_err)?;
+
+	Ok(Some(f))
+}
+
+fn handle_inotify_events(
+	inotify: &Inotify,
+	ep: &Epoll,
+	devices: &mut HashMap<u64, InputDevice>,
+	next_device_id: &mut u64,
+	match_name: Option<&str>,
+) {
+	let events = match inotify.read_events() {
+		Ok(events) => events,
+		Err(_) => return,
+	};
+
+	for event in events {
+		let Some(name) = event.name.as_ref() else {
+			continue;
+		};
+		let name = name.to_string_lossy();
+		if !name.starts_with("event") {
+			continue;
+		}
+		let path = Path::new("/dev/input").join(name.as_ref());
+
+		if event.mask.contains(AddWatchFlags::IN_CREATE) {
+			let Ok(Some(f)) = open_keyboard_device(&path, match_name) else {
+				continue;
+			};
+
+			let id = *next_device_id;
+			*next_device_id += 1;
+			if ep
+				.add(
+					&f,
+					EpollEvent::new(
+						EpollFlags::EPOLLIN | EpollFlags::EPOLLERR | EpollFlags::EPOLLHUP,
+						id,
+					),
+				)
+				.is_err()
+			{
+				continue;
+			}
+
+			devices.insert(id, InputDevice::new(path, f));
+		} else if event.mask.contains(AddWatchFlags::IN_DELETE) {
+			let removed_id = devices
+				.iter()
+				.find(|(_, dev)| dev.path == path)
+				.map(|(id, _)| *id);
+
+			if let Some(id) = removed_id {
+				if let Some(dev) = devices.remove(&id) {
+					let _ = ep.delete(&dev.file);
+				}
+			}
+		}
+	}
+}
+
 fn ms_to_timeout(ms: i64) -> PollTimeout {
 	if ms <= 0 {
 		return PollTimeout::from(0u16);
@@ -236,7 +797,7 @@ fn ms_to_timeout(ms: i64) -> PollTimeout {
 fn main() -> std::io::Result<()> {
 	let options = Options::parse();
 
-	let mut backlight = Backlight::open()?;
+	let mut backlight = Backlight::open(options.curve, options.gamma, options.color)?;
 	let initial_raw = backlight.read_raw()?;
 	let initial = backlight.raw_to_f32(initial_raw);
 
@@ -249,43 +810,74 @@ fn main() -> std::io::Result<()> {
 	let paths = get_all_input_devices()?;
 	let ep = Epoll::new(EpollCreateFlags::empty()).map_err(to_io_err)?;
 
-	let mut files = Vec::<File>::new();
+	let mut devices = HashMap::<u64, InputDevice>::new();
+	let mut next_device_id: u64 = 0;
+
 	for p in paths {
-		let f = match File::open(&p) {
-			Ok(f) => f,
-			Err(_) => continue,
+		let Some(f) = open_keyboard_device(&p, options.match_name.as_deref())? else {
+			continue;
 		};
 
-		let flags =
-			OFlag::from_bits_truncate(fcntl(f.as_fd(), FcntlArg::F_GETFL).map_err(to_io_err)?);
-		let new_flags = flags | OFlag::O_NONBLOCK;
-		fcntl(f.as_fd(), FcntlArg::F_SETFL(new_flags)).map_err(to_io_err)?;
-
-		let idx = files.len() as u64;
+		let id = next_device_id;
+		next_device_id += 1;
 		ep.add(
 			&f,
 			EpollEvent::new(
 				EpollFlags::EPOLLIN | EpollFlags::EPOLLERR | EpollFlags::EPOLLHUP,
-				idx,
+				id,
 			),
 		)
 		.map_err(to_io_err)?;
 
-		files.push(f);
+		devices.insert(id, InputDevice::new(p, f));
 	}
 
-	if files.is_empty() {
+	if devices.is_empty() {
 		return Err(std::io::Error::new(
 			std::io::ErrorKind::NotFound,
 			"No readable /dev/input/event* devices found",
 		));
 	}
 
+	// Watch for keyboards plugged in after startup.
+	let inotify = Inotify::init(InitFlags::IN_NONBLOCK).map_err(to_io_err)?;
+	inotify
+		.add_watch(
+			Path::new("/dev/input"),
+			AddWatchFlags::IN_CREATE | AddWatchFlags::IN_DELETE,
+		)
+		.map_err(to_io_err)?;
+	ep.add(
+		&inotify,
+		EpollEvent::new(EpollFlags::EPOLLIN, INOTIFY_TOKEN),
+	)
+	.map_err(to_io_err)?;
+
+	// Restore the backlight instead of leaving it dark if we're killed.
+	let mut sigmask = SigSet::empty();
+	sigmask.add(Signal::SIGTERM);
+	sigmask.add(Signal::SIGINT);
+	sigprocmask(SigmaskHow::SIG_BLOCK, Some(&sigmask), None).map_err(to_io_err)?;
+	let signal_fd = SignalFd::with_flags(&sigmask, SfdFlags::SFD_NONBLOCK).map_err(to_io_err)?;
+	ep.add(
+		&signal_fd,
+		EpollEvent::new(EpollFlags::EPOLLIN, SIGNAL_TOKEN),
+	)
+	.map_err(to_io_err)?;
+
+	let mut ambient = if options.ambient {
+		find_ambient_light_sensor().and_then(|source| {
+			AmbientLight::new(source, options.ambient_alpha, options.lux_min, options.lux_max).ok()
+		})
+	} else {
+		None
+	};
+
 	let mut ep_events = [EpollEvent::empty(); 64];
-	let mut junk = [0u8; 4096];
 
 	let start = Instant::now();
 	let mut last_activity = start;
+	let mut last_ambient_sample = start;
 
 	let mut saved_raw: Option<u32> = None;
 	let mut is_dimmed = false;
@@ -300,6 +892,9 @@ fn main() -> std::io::Result<()> {
 			next_wake = next_wake.min(now + Duration::from_millis(MIN_FADE_TICK_MS));
 		} else if is_dimmed {
 			next_wake = now + Duration::from_secs(60);
+		} else if ambient.is_some() {
+			next_wake =
+				next_wake.min(last_ambient_sample + Duration::from_millis(AMBIENT_SAMPLE_MS));
 		}
 
 		let timeout_ms = next_wake
@@ -314,40 +909,70 @@ fn main() -> std::io::Result<()> {
 		let now = Instant::now();
 
 		if n != 0 {
+			let mut saw_activity = false;
+			let mut should_exit = false;
+
 			for ev in ep_events.iter().take(n) {
-				let idx = ev.data() as usize;
-				if idx >= files.len() {
+				let token = ev.data();
+
+				if token == SIGNAL_TOKEN {
+					while signal_fd.read_signal().unwrap_or(None).is_some() {
+						should_exit = true;
+					}
+					continue;
+				}
+
+				if token == INOTIFY_TOKEN {
+					handle_inotify_events(
+						&inotify,
+						&ep,
+						&mut devices,
+						&mut next_device_id,
+						options.match_name.as_deref(),
+					);
 					continue;
 				}
 
+				let Some(device) = devices.get_mut(&token) else {
+					continue;
+				};
+
 				let flags = ev.events();
 				if flags.contains(EpollFlags::EPOLLERR) || flags.contains(EpollFlags::EPOLLHUP) {
-					let _ = ep.delete(&files[idx]);
+					let _ = ep.delete(&device.file);
+					devices.remove(&token);
 					continue;
 				}
 
-				loop {
-					match files[idx].read(&mut junk) {
-						Ok(0) => break,
-						Ok(_) => continue,
-						Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
-						Err(_) => {
-							let _ = ep.delete(&files[idx]);
-							break;
-						}
+				match device.drain_activity(&options.trigger) {
+					Ok(matched) => saw_activity |= matched,
+					Err(_) => {
+						let _ = ep.delete(&device.file);
+						devices.remove(&token);
 					}
 				}
 			}
 
-			last_activity = now;
-
-			if is_dimmed {
+			if should_exit {
+				let restore_raw = saved_raw.unwrap_or(initial_raw);
+				backlight.write_raw(restore_raw)?;
 				if options.verbose {
-					println!("Restoring keyboard brightness");
+					println!("Restoring keyboard brightness and exiting");
+				}
+				return Ok(());
+			}
+
+			if saw_activity {
+				last_activity = now;
+
+				if is_dimmed {
+					if options.verbose {
+						println!("Restoring keyboard brightness");
+					}
+					let restore_raw = saved_raw.take().unwrap_or(initial_raw);
+					fader.set_target(now, backlight.raw_to_f32(restore_raw));
+					is_dimmed = false;
 				}
-				let restore_raw = saved_raw.take().unwrap_or(initial_raw);
-				fader.set_target(now, backlight.raw_to_f32(restore_raw));
-				is_dimmed = false;
 			}
 		}
 
@@ -366,6 +991,19 @@ fn main() -> std::io::Result<()> {
 			}
 		}
 
+		// Sample on its own cadence regardless of whether this tick carried
+		// epoll events, so sustained input activity doesn't starve the sensor.
+		if !is_dimmed && now >= last_ambient_sample + Duration::from_millis(AMBIENT_SAMPLE_MS) {
+			if let Some(ambient) = &mut ambient {
+				last_ambient_sample = now;
+				// ambient.sample() is a linear lux-range fraction; invert the
+				// curve so it lands in the same perceptual space as every
+				// other set_target call (f32_to_raw curve-forward-maps it back).
+				let fraction = backlight.curve.invert(ambient.sample(), backlight.gamma);
+				fader.set_target(now, fraction);
+			}
+		}
+
 		let v = fader.value(now);
 		let raw = backlight.f32_to_raw(v);
 		backlight.write_raw(raw)?;